@@ -1,9 +1,11 @@
-use std::{collections::HashMap, fmt::Debug};
+use std::{collections::HashMap, fmt::Debug, sync::Arc};
 use tonic::transport::Endpoint;
 
 use crate::{
-    api::{Mutation, Request as DgraphRequest, Response},
+    api::{Mutation, Operation, Payload, Request as DgraphRequest, Response},
     error::Result,
+    metrics::PoolStats,
+    pool::{is_token_expired, relogin, Credentials, SharedJwt},
     DgraphConn,
 };
 
@@ -45,14 +47,27 @@ pub struct Client {
     best_effort: bool,
     read_only: bool,
     pub(crate) inner: DgraphConn,
+    jwt: SharedJwt,
+    credentials: Option<Credentials>,
+    stats: Arc<PoolStats>,
 }
 
 impl Client {
-    pub(crate) fn new(inner: DgraphConn, read_only: bool, best_effort: bool) -> Self {
+    pub(crate) fn new(
+        inner: DgraphConn,
+        read_only: bool,
+        best_effort: bool,
+        jwt: SharedJwt,
+        credentials: Option<Credentials>,
+        stats: Arc<PoolStats>,
+    ) -> Self {
         Self {
             best_effort,
             read_only,
             inner,
+            jwt,
+            credentials,
+            stats,
         }
     }
 
@@ -84,8 +99,22 @@ impl Client {
     }
 
     async fn do_request(&mut self, req: DgraphRequest) -> Result<Response> {
-        let response = self.inner.query(req).await?;
-        Ok(response.into_inner())
+        let _in_flight = self.stats.record_request_start();
+        let is_mutation = !req.mutations.is_empty();
+        let resp = match self.inner.query(req.clone()).await {
+            Ok(response) => response.into_inner(),
+            Err(status) if is_token_expired(&status) => {
+                relogin(&mut self.inner, &self.jwt, &self.credentials).await?;
+                self.inner.query(req).await?.into_inner()
+            }
+            Err(status) => return Err(status.into()),
+        };
+        if is_mutation {
+            self.stats.record_mutation(resp.latency.as_ref());
+        } else {
+            self.stats.record_query(resp.latency.as_ref());
+        }
+        Ok(resp)
     }
 
     pub async fn mutate(&mut self, mus: impl Into<Vec<Mutation>>) -> Result<Response> {
@@ -126,6 +155,18 @@ impl Client {
         };
         self.do_request(req).await
     }
+
+    pub async fn alter(&mut self, op: Operation) -> Result<Payload> {
+        match self.inner.alter(op.clone()).await {
+            Ok(resp) => Ok(resp.into_inner()),
+            Err(status) if is_token_expired(&status) => {
+                relogin(&mut self.inner, &self.jwt, &self.credentials).await?;
+                let resp = self.inner.alter(op).await?;
+                Ok(resp.into_inner())
+            }
+            Err(status) => Err(status.into()),
+        }
+    }
 }
 
 impl Debug for Client {
@@ -138,7 +179,7 @@ impl Debug for Client {
 
 #[cfg(test)]
 mod tests {
-    use crate::pool::DgraphPool;
+    use crate::pool::{DgraphPool, DgraphPoolConfig};
 
     use super::*;
 
@@ -147,7 +188,7 @@ mod tests {
     #[tokio::test]
     async fn test_upsert() {
         let server = EndpointAddresses::StaticStr(vec![DGRAPH_SERVER]);
-        let mut c = DgraphPool::new(server, 1)
+        let mut c = DgraphPool::new(server, DgraphPoolConfig::new(1), None)
             .await
             .unwrap()
             .get()
@@ -174,7 +215,7 @@ uid(envs) <env_systems> _:system ."#;
     #[tokio::test]
     async fn test_mutation() {
         let server = EndpointAddresses::StaticStr(vec![DGRAPH_SERVER]);
-        let pool = DgraphPool::new(server, 1).await.unwrap();
+        let pool = DgraphPool::new(server, DgraphPoolConfig::new(1), None).await.unwrap();
         let mut c = pool.get().await.unwrap();
         let q = r#"{
 q(func: eq(env_id, "prod")) {
@@ -203,7 +244,7 @@ _:system <dgraph.type> "System" .
     #[tokio::test]
     async fn test_txn() {
         let server = EndpointAddresses::StaticStr(vec![DGRAPH_SERVER]);
-        let c = DgraphPool::new(server, 1).await.unwrap();
+        let c = DgraphPool::new(server, DgraphPoolConfig::new(1), None).await.unwrap();
         let mut conn = c.get_best_effort().await.unwrap();
         let mut txn = c.new_txn().await.unwrap();
         let mut mu1 = Mutation::new();
@@ -1,13 +1,36 @@
 use deadpool::managed::{BuildError, PoolError};
 use thiserror::Error;
+use tonic::Code;
 
 #[derive(Error, Debug)]
 pub enum DgraphError {
     #[error("Transport error: {0}")]
     Transport(#[from] tonic::transport::Error),
 
+    /// The cluster (or a replica) is temporarily unreachable, e.g. during a
+    /// leader election or rollout. Safe to retry.
+    #[error("Dgraph unavailable: {0}")]
+    Unavailable(String),
+
+    /// The transaction was aborted by the server due to write conflicts.
+    /// Safe to retry with a fresh transaction.
+    #[error("Transaction aborted: {0}")]
+    Aborted(String),
+
+    /// The ACL user/group does not have the required permission, or the
+    /// provided credentials/tokens were rejected. Not retryable.
+    #[error("Permission denied: {0}")]
+    PermissionDenied(String),
+
+    /// The schema/mutation was rejected, e.g. a type conflict or malformed
+    /// schema. Not retryable.
+    #[error("Schema error: {0}")]
+    Schema(String),
+
+    /// Any other gRPC status that doesn't fall into one of the classified
+    /// variants above.
     #[error("gRPC status error: {0}")]
-    Status(#[from] tonic::Status),
+    Status(tonic::Status),
 
     #[error("Connection pool error: {0}")]
     Pool(#[from] BuildError),
@@ -22,4 +45,148 @@ pub enum DgraphError {
     PoolRunError(#[from] PoolError<tonic::Status>),
 }
 
-pub type Result<T> = std::result::Result<T, DgraphError>; 
\ No newline at end of file
+impl DgraphError {
+    /// Whether retrying the call that produced this error has a reasonable
+    /// chance of succeeding, e.g. transient unavailability or a transaction
+    /// abort due to write contention. Permission and schema errors are
+    /// permanent and should not be retried.
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, DgraphError::Unavailable(_) | DgraphError::Aborted(_))
+    }
+}
+
+impl From<tonic::Status> for DgraphError {
+    fn from(status: tonic::Status) -> Self {
+        let message = status.message().to_string();
+        match status.code() {
+            Code::Unavailable | Code::DeadlineExceeded => DgraphError::Unavailable(message),
+            Code::Aborted => DgraphError::Aborted(message),
+            Code::PermissionDenied | Code::Unauthenticated => {
+                DgraphError::PermissionDenied(message)
+            }
+            Code::InvalidArgument if is_schema_message(&message) => DgraphError::Schema(message),
+            _ if is_conflict_message(&message) => DgraphError::Aborted(message),
+            _ if is_leader_change_message(&message) => DgraphError::Unavailable(message),
+            _ => DgraphError::Status(status),
+        }
+    }
+}
+
+fn is_schema_message(message: &str) -> bool {
+    message.contains("schema") || message.contains("predicate")
+}
+
+fn is_conflict_message(message: &str) -> bool {
+    message.contains("Transaction has been aborted") || message.contains("Please retry")
+}
+
+fn is_leader_change_message(message: &str) -> bool {
+    message.contains("leader") && (message.contains("changed") || message.contains("change"))
+}
+
+pub type Result<T> = std::result::Result<T, DgraphError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tonic::Status;
+
+    #[test]
+    fn is_retryable_only_for_unavailable_and_aborted() {
+        assert!(DgraphError::Unavailable("x".to_string()).is_retryable());
+        assert!(DgraphError::Aborted("x".to_string()).is_retryable());
+        assert!(!DgraphError::PermissionDenied("x".to_string()).is_retryable());
+        assert!(!DgraphError::Schema("x".to_string()).is_retryable());
+        assert!(!DgraphError::InvalidArgument("x".to_string()).is_retryable());
+    }
+
+    #[test]
+    fn classifies_unavailable_and_deadline_exceeded() {
+        assert!(matches!(
+            DgraphError::from(Status::new(Code::Unavailable, "down")),
+            DgraphError::Unavailable(_)
+        ));
+        assert!(matches!(
+            DgraphError::from(Status::new(Code::DeadlineExceeded, "slow")),
+            DgraphError::Unavailable(_)
+        ));
+    }
+
+    #[test]
+    fn classifies_aborted() {
+        assert!(matches!(
+            DgraphError::from(Status::new(Code::Aborted, "conflict")),
+            DgraphError::Aborted(_)
+        ));
+    }
+
+    #[test]
+    fn classifies_permission_denied_and_unauthenticated() {
+        assert!(matches!(
+            DgraphError::from(Status::new(Code::PermissionDenied, "nope")),
+            DgraphError::PermissionDenied(_)
+        ));
+        assert!(matches!(
+            DgraphError::from(Status::new(Code::Unauthenticated, "Token is expired")),
+            DgraphError::PermissionDenied(_)
+        ));
+    }
+
+    #[test]
+    fn classifies_schema_errors_by_message() {
+        assert!(matches!(
+            DgraphError::from(Status::new(Code::InvalidArgument, "schema: predicate type mismatch")),
+            DgraphError::Schema(_)
+        ));
+        assert!(matches!(
+            DgraphError::from(Status::new(Code::InvalidArgument, "bad uid")),
+            DgraphError::Status(_)
+        ));
+    }
+
+    #[test]
+    fn classifies_write_conflicts_as_aborted_regardless_of_code() {
+        assert!(matches!(
+            DgraphError::from(Status::new(Code::Unknown, "Transaction has been aborted. Please retry")),
+            DgraphError::Aborted(_)
+        ));
+    }
+
+    #[test]
+    fn classifies_leader_change_as_unavailable_regardless_of_code() {
+        assert!(matches!(
+            DgraphError::from(Status::new(Code::Unknown, "the leader has changed")),
+            DgraphError::Unavailable(_)
+        ));
+    }
+
+    #[test]
+    fn falls_back_to_status_for_unrecognized_errors() {
+        assert!(matches!(
+            DgraphError::from(Status::new(Code::Internal, "something else")),
+            DgraphError::Status(_)
+        ));
+    }
+
+    #[test]
+    fn is_schema_message_matches_schema_and_predicate() {
+        assert!(is_schema_message("schema update failed"));
+        assert!(is_schema_message("unknown predicate foo"));
+        assert!(!is_schema_message("unrelated"));
+    }
+
+    #[test]
+    fn is_conflict_message_matches_known_phrasings() {
+        assert!(is_conflict_message("Transaction has been aborted. Please retry"));
+        assert!(is_conflict_message("Please retry later"));
+        assert!(!is_conflict_message("unrelated"));
+    }
+
+    #[test]
+    fn is_leader_change_message_requires_both_words() {
+        assert!(is_leader_change_message("leader has changed"));
+        assert!(is_leader_change_message("the leader change event"));
+        assert!(!is_leader_change_message("leader is fine"));
+        assert!(!is_leader_change_message("something changed"));
+    }
+}
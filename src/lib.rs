@@ -1,6 +1,7 @@
 mod api;
 pub mod client;
 pub mod error;
+pub mod metrics;
 pub mod pool;
 pub mod transaction;
 
@@ -9,7 +10,8 @@ use std::fmt::Display;
 pub use api::{Latency, Metrics, Mutation, Operation, Payload, Response, operation::DropOp};
 pub use client::{Client, EndpointAddresses};
 pub use error::{DgraphError, Result};
-pub use pool::{DgraphConn, DgraphPool};
+pub use metrics::PoolStatsSnapshot;
+pub use pool::{Credentials, DgraphConn, DgraphPool, DgraphPoolConfig};
 pub use transaction::Transaction;
 
 impl Mutation {
@@ -36,6 +38,38 @@ impl From<Mutation> for Vec<Mutation> {
     }
 }
 
+impl Operation {
+    pub fn schema(schema: impl Into<String>) -> Operation {
+        Operation {
+            schema: schema.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn drop_all() -> Operation {
+        Operation {
+            drop_op: DropOp::All as i32,
+            ..Default::default()
+        }
+    }
+
+    pub fn drop_attr(name: impl Into<String>) -> Operation {
+        Operation {
+            drop_op: DropOp::Attr as i32,
+            drop_value: name.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn drop_type(name: impl Into<String>) -> Operation {
+        Operation {
+            drop_op: DropOp::Type as i32,
+            drop_value: name.into(),
+            ..Default::default()
+        }
+    }
+}
+
 impl Display for Mutation {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         writeln!(f, "Mutation {{")?;
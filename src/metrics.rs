@@ -0,0 +1,184 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::api::Latency;
+
+/// Upper bounds (inclusive, in milliseconds) of the processing-latency
+/// histogram buckets. A sample that exceeds every bound falls into an
+/// implicit `+Inf` bucket.
+const LATENCY_BUCKETS_MS: [u64; 10] = [1, 2, 5, 10, 20, 50, 100, 250, 500, 1000];
+
+#[derive(Debug, Default)]
+struct LatencyHistogram {
+    buckets: [AtomicU64; LATENCY_BUCKETS_MS.len() + 1],
+}
+
+impl LatencyHistogram {
+    fn observe(&self, value_ms: u64) {
+        let idx = LATENCY_BUCKETS_MS
+            .iter()
+            .position(|&bound| value_ms <= bound)
+            .unwrap_or(LATENCY_BUCKETS_MS.len());
+        self.buckets[idx].fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Estimates the given percentile (0.0..=1.0) from the bucket counts.
+    /// The result is only as precise as `LATENCY_BUCKETS_MS`.
+    fn percentile(&self, p: f64) -> u64 {
+        let counts: Vec<u64> = self
+            .buckets
+            .iter()
+            .map(|bucket| bucket.load(Ordering::Relaxed))
+            .collect();
+        let total: u64 = counts.iter().sum();
+        if total == 0 {
+            return 0;
+        }
+        let target = ((total as f64) * p).ceil() as u64;
+        let mut cumulative = 0;
+        for (idx, count) in counts.iter().enumerate() {
+            cumulative += count;
+            if cumulative >= target {
+                return *LATENCY_BUCKETS_MS
+                    .get(idx)
+                    .unwrap_or_else(|| LATENCY_BUCKETS_MS.last().unwrap());
+            }
+        }
+        *LATENCY_BUCKETS_MS.last().unwrap()
+    }
+}
+
+/// Tracks pooled-connection checkouts, in-flight requests, and server-reported
+/// latency for a `DgraphPool`. Cheap to update (plain atomics), so it is
+/// always on; `snapshot()` is the read side.
+#[derive(Debug, Default)]
+pub struct PoolStats {
+    checkouts: AtomicU64,
+    in_flight: AtomicU64,
+    queries: AtomicU64,
+    mutations: AtomicU64,
+    aborted_txns: AtomicU64,
+    processing_latency: LatencyHistogram,
+}
+
+/// RAII guard that keeps `PoolStats::in_flight` accurate across early
+/// returns (`?`) from `do_request`.
+pub(crate) struct InFlightGuard<'a> {
+    stats: &'a PoolStats,
+}
+
+impl Drop for InFlightGuard<'_> {
+    fn drop(&mut self) {
+        self.stats.in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl PoolStats {
+    pub(crate) fn record_checkout(&self) {
+        self.checkouts.fetch_add(1, Ordering::Relaxed);
+        #[cfg(feature = "metrics")]
+        metrics::counter!("dgraph_conn_pool_checkouts_total").increment(1);
+    }
+
+    pub(crate) fn record_request_start(&self) -> InFlightGuard<'_> {
+        self.in_flight.fetch_add(1, Ordering::Relaxed);
+        InFlightGuard { stats: self }
+    }
+
+    pub(crate) fn record_query(&self, latency: Option<&Latency>) {
+        self.queries.fetch_add(1, Ordering::Relaxed);
+        self.observe_latency(latency);
+        #[cfg(feature = "metrics")]
+        metrics::counter!("dgraph_conn_queries_total").increment(1);
+    }
+
+    pub(crate) fn record_mutation(&self, latency: Option<&Latency>) {
+        self.mutations.fetch_add(1, Ordering::Relaxed);
+        self.observe_latency(latency);
+        #[cfg(feature = "metrics")]
+        metrics::counter!("dgraph_conn_mutations_total").increment(1);
+    }
+
+    pub(crate) fn record_aborted_txn(&self) {
+        self.aborted_txns.fetch_add(1, Ordering::Relaxed);
+        #[cfg(feature = "metrics")]
+        metrics::counter!("dgraph_conn_aborted_txns_total").increment(1);
+    }
+
+    fn observe_latency(&self, latency: Option<&Latency>) {
+        let Some(latency) = latency else {
+            return;
+        };
+        let processing_ms = latency.processing_ns / 1_000_000;
+        self.processing_latency.observe(processing_ms);
+        #[cfg(feature = "metrics")]
+        metrics::histogram!("dgraph_conn_query_processing_latency_ms").record(processing_ms as f64);
+    }
+
+    pub fn snapshot(&self) -> PoolStatsSnapshot {
+        PoolStatsSnapshot {
+            checkouts: self.checkouts.load(Ordering::Relaxed),
+            in_flight: self.in_flight.load(Ordering::Relaxed),
+            queries: self.queries.load(Ordering::Relaxed),
+            mutations: self.mutations.load(Ordering::Relaxed),
+            aborted_txns: self.aborted_txns.load(Ordering::Relaxed),
+            processing_latency_p50_ms: self.processing_latency.percentile(0.5),
+            processing_latency_p99_ms: self.processing_latency.percentile(0.99),
+        }
+    }
+}
+
+/// Point-in-time snapshot of [`PoolStats`], returned by `DgraphPool::stats()`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PoolStatsSnapshot {
+    pub checkouts: u64,
+    pub in_flight: u64,
+    pub queries: u64,
+    pub mutations: u64,
+    pub aborted_txns: u64,
+    pub processing_latency_p50_ms: u64,
+    pub processing_latency_p99_ms: u64,
+    /// Total pooled connections currently alive (deadpool's `Status::size`).
+    pub live_connections: usize,
+    /// Of `live_connections`, how many are sitting idle in the pool right now.
+    pub idle_connections: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percentile_of_empty_histogram_is_zero() {
+        let hist = LatencyHistogram::default();
+        assert_eq!(hist.percentile(0.5), 0);
+        assert_eq!(hist.percentile(0.99), 0);
+    }
+
+    #[test]
+    fn percentile_picks_the_bucket_bound_containing_the_sample() {
+        let hist = LatencyHistogram::default();
+        hist.observe(3);
+        assert_eq!(hist.percentile(0.5), 5);
+        assert_eq!(hist.percentile(1.0), 5);
+    }
+
+    #[test]
+    fn percentile_over_many_samples_reflects_the_distribution() {
+        let hist = LatencyHistogram::default();
+        for _ in 0..90 {
+            hist.observe(1);
+        }
+        for _ in 0..10 {
+            hist.observe(1000);
+        }
+        assert_eq!(hist.percentile(0.5), 1);
+        assert_eq!(hist.percentile(0.99), 1000);
+    }
+
+    #[test]
+    fn percentile_of_sample_beyond_every_bucket_falls_into_the_last_bound() {
+        let hist = LatencyHistogram::default();
+        hist.observe(5_000);
+        assert_eq!(hist.percentile(1.0), *LATENCY_BUCKETS_MS.last().unwrap());
+    }
+}
@@ -1,83 +1,649 @@
-use deadpool::managed::{Manager, Metrics, Object, Pool, PoolConfig};
+use std::future::Future;
+use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use deadpool::managed::{Manager, Metrics, Object, Pool, PoolConfig, RecycleError, Timeouts};
+use prost::Message;
+use tokio::sync::RwLock;
+use tonic::service::Interceptor;
 use tonic::transport::Channel;
+use tonic::Status;
 
 use crate::api::dgraph_client::DgraphClient;
-use crate::api::Check;
+use crate::api::{Check, Jwt, LoginRequest, Operation, Payload};
 use crate::client::{Client, EndpointAddresses};
 use crate::error::Result;
+use crate::metrics::{PoolStats, PoolStatsSnapshot};
 use crate::{DgraphError, Transaction};
 
+/// Credentials used to log in to an ACL-enabled (Enterprise) Dgraph cluster.
+#[derive(Debug, Clone)]
+pub struct Credentials {
+    pub userid: String,
+    pub password: String,
+    pub namespace: Option<u64>,
+}
+
+impl Credentials {
+    pub fn new(userid: impl Into<String>, password: impl Into<String>) -> Self {
+        Self {
+            userid: userid.into(),
+            password: password.into(),
+            namespace: None,
+        }
+    }
+
+    pub fn with_namespace(mut self, namespace: u64) -> Self {
+        self.namespace = Some(namespace);
+        self
+    }
+
+    fn login_request(&self) -> LoginRequest {
+        LoginRequest {
+            userid: self.userid.clone(),
+            password: self.password.clone(),
+            namespace: self.namespace.unwrap_or_default(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Jwt pair shared by every pooled connection. Wrapped in `Arc<RwLock<..>>`
+/// so a refresh on one connection unblocks every other `DgraphClient` in the pool.
+pub(crate) type SharedJwt = Arc<RwLock<Jwt>>;
+
+/// Attaches the current `access_jwt` to every outgoing RPC as the `accessjwt`
+/// metadata header required by ACL-enabled clusters.
+#[derive(Clone)]
+pub(crate) struct AclInterceptor {
+    jwt: SharedJwt,
+}
+
+impl Interceptor for AclInterceptor {
+    fn call(&mut self, mut req: tonic::Request<()>) -> std::result::Result<tonic::Request<()>, Status> {
+        // `Interceptor::call` is a sync fn (tonic's trait, not ours), so the
+        // shared jwt can only be peeked with try_read, not awaited. If a
+        // concurrent relogin is mid-write this falls through and sends the
+        // request with no accessjwt header; the server then rejects it as a
+        // generic PermissionDenied/Unauthenticated rather than "Token is
+        // expired", so it won't be retried by `is_token_expired`. The write
+        // critical section in `relogin`/`ensure_logged_in` is a single
+        // assignment, so the window is tiny and self-heals on the caller's
+        // next request once the new token has landed.
+        if let Ok(jwt) = self.jwt.try_read() {
+            if !jwt.access_jwt.is_empty() {
+                if let Ok(value) = jwt.access_jwt.parse() {
+                    req.metadata_mut().insert("accessjwt", value);
+                }
+            }
+        }
+        Ok(req)
+    }
+}
+
+type AclChannel = tonic::service::interceptor::InterceptedService<Channel, AclInterceptor>;
+
+/// Pool sizing and connection lifecycle knobs.
+///
+/// Three independent controls, all enforced from `recycle`:
+/// - `health_check_interval`: the `check_version` ping used to run on every
+///   single checkout; now it only runs once a connection hasn't been
+///   validated for at least this long.
+/// - `idle_timeout`: connections that have sat unused in the pool longer
+///   than this are dropped and recreated rather than handed out.
+/// - `max_lifetime`: connections are dropped and recreated once they get
+///   this old, regardless of how busy or idle they've been.
+#[derive(Debug, Clone)]
+pub struct DgraphPoolConfig {
+    pub pool_size: usize,
+    pub acquire_timeout: Option<Duration>,
+    pub max_lifetime: Option<Duration>,
+    pub idle_timeout: Option<Duration>,
+    pub health_check_interval: Duration,
+}
+
+impl DgraphPoolConfig {
+    pub fn new(pool_size: usize) -> Self {
+        Self {
+            pool_size,
+            acquire_timeout: None,
+            max_lifetime: None,
+            idle_timeout: None,
+            health_check_interval: Duration::from_secs(30),
+        }
+    }
+
+    pub fn with_acquire_timeout(mut self, timeout: Duration) -> Self {
+        self.acquire_timeout = Some(timeout);
+        self
+    }
+
+    pub fn with_max_lifetime(mut self, lifetime: Duration) -> Self {
+        self.max_lifetime = Some(lifetime);
+        self
+    }
+
+    pub fn with_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.idle_timeout = Some(timeout);
+        self
+    }
+
+    pub fn with_health_check_interval(mut self, interval: Duration) -> Self {
+        self.health_check_interval = interval;
+        self
+    }
+}
+
+/// A pooled `DgraphClient` plus the bookkeeping `recycle` needs to avoid
+/// pinging the server on every checkout.
+pub struct PooledClient {
+    client: DgraphClient<AclChannel>,
+    created_at: Instant,
+    last_checked: Instant,
+}
+
+impl Deref for PooledClient {
+    type Target = DgraphClient<AclChannel>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.client
+    }
+}
+
+impl DerefMut for PooledClient {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.client
+    }
+}
+
+async fn login(
+    channel: Channel,
+    credentials: &Credentials,
+) -> std::result::Result<Jwt, Status> {
+    let mut client = DgraphClient::new(channel);
+    let resp = client.login(credentials.login_request()).await?.into_inner();
+    Jwt::decode(resp.json.as_slice())
+        .map_err(|e| Status::internal(format!("failed to decode login response: {e}")))
+}
+
 #[derive(Debug)]
 pub struct DgraphConnectionManager {
     endpoints: EndpointAddresses,
+    credentials: Option<Credentials>,
+    jwt: SharedJwt,
+    max_lifetime: Option<Duration>,
+    idle_timeout: Option<Duration>,
+    health_check_interval: Duration,
 }
 
 impl DgraphConnectionManager {
-    pub fn new(endpoints: EndpointAddresses) -> Self {
-        Self { endpoints }
+    pub fn new(endpoints: EndpointAddresses, config: &DgraphPoolConfig) -> Self {
+        Self {
+            endpoints,
+            credentials: None,
+            jwt: Arc::new(RwLock::new(Jwt::default())),
+            max_lifetime: config.max_lifetime,
+            idle_timeout: config.idle_timeout,
+            health_check_interval: config.health_check_interval,
+        }
+    }
+
+    pub fn with_credentials(
+        endpoints: EndpointAddresses,
+        credentials: Credentials,
+        config: &DgraphPoolConfig,
+    ) -> Self {
+        Self {
+            endpoints,
+            credentials: Some(credentials),
+            jwt: Arc::new(RwLock::new(Jwt::default())),
+            max_lifetime: config.max_lifetime,
+            idle_timeout: config.idle_timeout,
+            health_check_interval: config.health_check_interval,
+        }
+    }
+
+    /// Logs in and populates the shared `jwt` if credentials were configured
+    /// and no token has been obtained yet. The tokens are pool-wide, so this
+    /// only needs to happen once no matter how many connections get created.
+    async fn ensure_logged_in(&self) -> std::result::Result<(), tonic::Status> {
+        let Some(credentials) = &self.credentials else {
+            return Ok(());
+        };
+        if !self.jwt.read().await.access_jwt.is_empty() {
+            return Ok(());
+        }
+        let channel = Channel::balance_list(self.endpoints.to_endpoints().into_iter());
+        let jwt = login(channel, credentials).await?;
+        *self.jwt.write().await = jwt;
+        Ok(())
     }
 }
 
 impl Manager for DgraphConnectionManager {
-    type Type = DgraphClient<Channel>;
+    type Type = PooledClient;
     type Error = tonic::Status;
 
     async fn create(&self) -> std::result::Result<Self::Type, Self::Error> {
+        self.ensure_logged_in().await?;
+
         let channel = Channel::balance_list(self.endpoints.to_endpoints().into_iter());
-        Ok(DgraphClient::new(channel))
+        let client = DgraphClient::with_interceptor(
+            channel,
+            AclInterceptor {
+                jwt: self.jwt.clone(),
+            },
+        );
+        let now = Instant::now();
+        Ok(PooledClient {
+            client,
+            created_at: now,
+            last_checked: now,
+        })
     }
 
     async fn recycle(
         &self,
-        client: &mut Self::Type,
-        _metrics: &Metrics,
+        conn: &mut Self::Type,
+        metrics: &Metrics,
     ) -> deadpool::managed::RecycleResult<Self::Error> {
-        // DgraphClient::new(client).check_version(Check {}).await?;
-        client.check_version(Check {}).await?;
+        let idle_since = metrics.recycled.unwrap_or(metrics.created);
+        match recycle_decision(
+            conn.created_at.elapsed(),
+            idle_since.elapsed(),
+            conn.last_checked.elapsed(),
+            self.max_lifetime,
+            self.idle_timeout,
+            self.health_check_interval,
+        ) {
+            RecycleDecision::Evict(reason) => return Err(RecycleError::message(reason)),
+            RecycleDecision::Healthy => return Ok(()),
+            RecycleDecision::NeedsHealthCheck => {}
+        }
+        conn.client.check_version(Check {}).await?;
+        conn.last_checked = Instant::now();
         Ok(())
     }
 }
 
+/// Outcome of evaluating a pooled connection's `max_lifetime`/`idle_timeout`/
+/// `health_check_interval` against its age, precedence as listed: a stale
+/// lifetime or idle connection is evicted before a health check is even
+/// considered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RecycleDecision {
+    Evict(&'static str),
+    NeedsHealthCheck,
+    Healthy,
+}
+
+fn recycle_decision(
+    created_elapsed: Duration,
+    idle_elapsed: Duration,
+    last_checked_elapsed: Duration,
+    max_lifetime: Option<Duration>,
+    idle_timeout: Option<Duration>,
+    health_check_interval: Duration,
+) -> RecycleDecision {
+    if let Some(max_lifetime) = max_lifetime {
+        if created_elapsed > max_lifetime {
+            return RecycleDecision::Evict("max connection lifetime exceeded");
+        }
+    }
+    if let Some(idle_timeout) = idle_timeout {
+        if idle_elapsed > idle_timeout {
+            return RecycleDecision::Evict("connection exceeded idle timeout");
+        }
+    }
+    if last_checked_elapsed < health_check_interval {
+        return RecycleDecision::Healthy;
+    }
+    RecycleDecision::NeedsHealthCheck
+}
+
 #[derive(Clone)]
 pub struct DgraphPool {
     pool: Pool<DgraphConnectionManager>,
+    credentials: Option<Credentials>,
+    jwt: SharedJwt,
+    stats: Arc<PoolStats>,
 }
 
 impl DgraphPool {
-    pub async fn new(endpoints: EndpointAddresses, pool_size: usize) -> Result<Self> {
-        let manager = DgraphConnectionManager::new(endpoints);
+    pub async fn new(
+        endpoints: EndpointAddresses,
+        config: DgraphPoolConfig,
+        credentials: Option<Credentials>,
+    ) -> Result<Self> {
+        let manager = match credentials.clone() {
+            Some(credentials) => {
+                DgraphConnectionManager::with_credentials(endpoints, credentials, &config)
+            }
+            None => DgraphConnectionManager::new(endpoints, &config),
+        };
+        manager.ensure_logged_in().await.map_err(DgraphError::from)?;
+        let jwt = manager.jwt.clone();
 
+        let pool_config = PoolConfig {
+            max_size: config.pool_size,
+            timeouts: Timeouts {
+                wait: config.acquire_timeout,
+                ..Timeouts::default()
+            },
+            ..PoolConfig::default()
+        };
         let pool = Pool::builder(manager)
-            .config(PoolConfig::new(pool_size))
+            .config(pool_config)
             .build()
             .map_err(DgraphError::from)?;
 
-        Ok(Self { pool })
+        Ok(Self {
+            pool,
+            credentials,
+            jwt,
+            stats: Arc::new(PoolStats::default()),
+        })
     }
 
     pub fn pool(&self) -> &Pool<DgraphConnectionManager> {
         &self.pool
     }
 
+    /// Returns a point-in-time snapshot of pool checkout counts, in-flight
+    /// requests, server-reported query latency, and live/idle connections.
+    pub fn stats(&self) -> PoolStatsSnapshot {
+        let mut snapshot = self.stats.snapshot();
+        let status = self.pool.status();
+        snapshot.live_connections = status.size;
+        snapshot.idle_connections = status.available;
+        snapshot
+    }
+
     pub async fn get_readonly(&self) -> Result<Client> {
         let x = self.pool.get().await?;
-        Ok(Client::new(x, true, false))
+        self.stats.record_checkout();
+        Ok(Client::new(
+            x,
+            true,
+            false,
+            self.jwt.clone(),
+            self.credentials.clone(),
+            self.stats.clone(),
+        ))
     }
 
     pub async fn get_best_effort(&self) -> Result<Client> {
         let x = self.pool.get().await?;
-        Ok(Client::new(x, true, true))
+        self.stats.record_checkout();
+        Ok(Client::new(
+            x,
+            true,
+            true,
+            self.jwt.clone(),
+            self.credentials.clone(),
+            self.stats.clone(),
+        ))
     }
 
     pub async fn get(&self) -> Result<Client> {
         let x = self.pool.get().await?;
-        Ok(Client::new(x, false, false))
+        self.stats.record_checkout();
+        Ok(Client::new(
+            x,
+            false,
+            false,
+            self.jwt.clone(),
+            self.credentials.clone(),
+            self.stats.clone(),
+        ))
     }
 
     pub async fn new_txn(&self) -> Result<Transaction> {
         let conn = self.pool.get().await?;
-        Ok(Transaction::new(conn))
+        self.stats.record_checkout();
+        Ok(Transaction::new(
+            conn,
+            self.jwt.clone(),
+            self.credentials.clone(),
+            self.stats.clone(),
+        ))
+    }
+
+    pub async fn alter(&self, op: Operation) -> Result<Payload> {
+        self.get().await?.alter(op).await
     }
+
+    /// Runs `f` inside a fresh transaction, committing on success. If the
+    /// transaction is aborted by the server due to write contention, the
+    /// whole closure is retried from scratch (a new `Transaction`, i.e. a new
+    /// `start_ts`, for every attempt) with capped exponential backoff.
+    pub async fn run_txn<F, Fut, T>(&self, f: F) -> Result<T>
+    where
+        F: FnMut(&mut Transaction) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        self.run_txn_with_attempts(DEFAULT_MAX_TXN_ATTEMPTS, f).await
+    }
+
+    pub async fn run_txn_with_attempts<F, Fut, T>(&self, max_attempts: u32, mut f: F) -> Result<T>
+    where
+        F: FnMut(&mut Transaction) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let mut txn = self.new_txn().await?;
+            match f(&mut txn).await {
+                Ok(value) => match txn.commit().await {
+                    Ok(()) => return Ok(value),
+                    Err(err) => {
+                        // CommitOrAbort is where Dgraph actually reports most
+                        // real-world aborts, so this needs the same retry
+                        // treatment as an error from the closure itself.
+                        // (`Transaction::commit_or_abort` already bumped
+                        // `aborted_txns` for us.)
+                        if attempt >= max_attempts || !err.is_retryable() {
+                            return Err(err);
+                        }
+                        tokio::time::sleep(txn_backoff(attempt)).await;
+                    }
+                },
+                Err(err) => {
+                    let _ = txn.discard().await;
+                    if matches!(err, DgraphError::Aborted(_)) {
+                        self.stats.record_aborted_txn();
+                    }
+                    if attempt >= max_attempts || !err.is_retryable() {
+                        return Err(err);
+                    }
+                    tokio::time::sleep(txn_backoff(attempt)).await;
+                }
+            }
+        }
+    }
+}
+
+const DEFAULT_MAX_TXN_ATTEMPTS: u32 = 5;
+const TXN_BACKOFF_BASE: Duration = Duration::from_millis(10);
+const TXN_BACKOFF_MAX: Duration = Duration::from_secs(1);
+
+/// Capped exponential backoff with full jitter: a random delay between zero
+/// and `min(TXN_BACKOFF_MAX, TXN_BACKOFF_BASE * 2^(attempt - 1))`.
+fn txn_backoff(attempt: u32) -> Duration {
+    let exp = TXN_BACKOFF_BASE
+        .as_millis()
+        .saturating_mul(1u128 << attempt.saturating_sub(1).min(10));
+    let cap = exp.min(TXN_BACKOFF_MAX.as_millis()).max(1);
+    let jitter = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .subsec_nanos() as u128
+        % cap;
+    Duration::from_millis(jitter as u64)
 }
 
 pub type DgraphConn = Object<DgraphConnectionManager>;
+
+/// Re-logs in using the refresh token, falling back to fresh credentials if the
+/// refresh token itself has expired, and stores the resulting tokens for the
+/// whole pool to pick up.
+pub(crate) async fn relogin(
+    conn: &mut DgraphConn,
+    jwt: &SharedJwt,
+    credentials: &Option<Credentials>,
+) -> Result<()> {
+    let refresh_jwt = jwt.read().await.refresh_jwt.clone();
+
+    let resp = if !refresh_jwt.is_empty() {
+        let req = LoginRequest {
+            refresh_token: refresh_jwt,
+            ..Default::default()
+        };
+        conn.login(req).await
+    } else {
+        Err(Status::unauthenticated("no refresh token available"))
+    };
+
+    let resp = match resp {
+        Ok(resp) => resp,
+        Err(_) => {
+            let credentials = credentials.as_ref().ok_or_else(|| {
+                DgraphError::InvalidArgument(
+                    "access token expired and no credentials available to re-login".to_string(),
+                )
+            })?;
+            conn.login(credentials.login_request()).await?
+        }
+    };
+
+    let new_jwt = Jwt::decode(resp.into_inner().json.as_slice())
+        .map_err(|e| DgraphError::InvalidArgument(format!("failed to decode jwt: {e}")))?;
+    *jwt.write().await = new_jwt;
+    Ok(())
+}
+
+pub(crate) fn is_token_expired(status: &tonic::Status) -> bool {
+    status.message().contains("Token is expired")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn txn_backoff_on_first_attempt_stays_below_the_base() {
+        // attempt 1 => cap == TXN_BACKOFF_BASE, jitter is in [0, cap)
+        assert!(txn_backoff(1) < TXN_BACKOFF_BASE);
+    }
+
+    #[test]
+    fn txn_backoff_cap_grows_monotonically_until_the_ceiling() {
+        let mut prev_cap = 0;
+        for attempt in 1..=8 {
+            let cap = TXN_BACKOFF_BASE
+                .as_millis()
+                .saturating_mul(1u128 << (attempt - 1));
+            let cap = cap.min(TXN_BACKOFF_MAX.as_millis());
+            assert!(cap >= prev_cap, "cap should never shrink between attempts");
+            prev_cap = cap;
+        }
+    }
+
+    #[test]
+    fn txn_backoff_never_exceeds_the_configured_max() {
+        for attempt in [1, 2, 5, 10, 50, u32::MAX] {
+            assert!(txn_backoff(attempt) <= TXN_BACKOFF_MAX);
+        }
+    }
+
+    #[test]
+    fn txn_backoff_handles_attempt_zero_without_panicking() {
+        assert!(txn_backoff(0) <= TXN_BACKOFF_MAX);
+    }
+
+    #[test]
+    fn recycle_decision_max_lifetime_takes_precedence_over_idle_and_health() {
+        let decision = recycle_decision(
+            Duration::from_secs(61),
+            Duration::ZERO,
+            Duration::ZERO,
+            Some(Duration::from_secs(60)),
+            Some(Duration::from_secs(3600)),
+            Duration::from_secs(30),
+        );
+        assert_eq!(
+            decision,
+            RecycleDecision::Evict("max connection lifetime exceeded")
+        );
+    }
+
+    #[test]
+    fn recycle_decision_idle_timeout_evicts_when_not_yet_past_max_lifetime() {
+        let decision = recycle_decision(
+            Duration::from_secs(1),
+            Duration::from_secs(61),
+            Duration::ZERO,
+            Some(Duration::from_secs(3600)),
+            Some(Duration::from_secs(60)),
+            Duration::from_secs(30),
+        );
+        assert_eq!(
+            decision,
+            RecycleDecision::Evict("connection exceeded idle timeout")
+        );
+    }
+
+    #[test]
+    fn recycle_decision_boundary_equal_to_threshold_does_not_evict() {
+        let decision = recycle_decision(
+            Duration::from_secs(60),
+            Duration::from_secs(60),
+            Duration::ZERO,
+            Some(Duration::from_secs(60)),
+            Some(Duration::from_secs(60)),
+            Duration::from_secs(30),
+        );
+        assert_eq!(decision, RecycleDecision::Healthy);
+    }
+
+    #[test]
+    fn recycle_decision_with_no_lifetime_or_idle_config_only_checks_health() {
+        let decision = recycle_decision(
+            Duration::from_secs(1_000_000),
+            Duration::from_secs(1_000_000),
+            Duration::from_secs(31),
+            None,
+            None,
+            Duration::from_secs(30),
+        );
+        assert_eq!(decision, RecycleDecision::NeedsHealthCheck);
+    }
+
+    #[test]
+    fn recycle_decision_healthy_when_checked_recently() {
+        let decision = recycle_decision(
+            Duration::from_secs(1),
+            Duration::from_secs(1),
+            Duration::from_secs(1),
+            None,
+            None,
+            Duration::from_secs(30),
+        );
+        assert_eq!(decision, RecycleDecision::Healthy);
+    }
+
+    #[test]
+    fn recycle_decision_needs_health_check_once_interval_elapsed() {
+        let decision = recycle_decision(
+            Duration::from_secs(1),
+            Duration::from_secs(1),
+            Duration::from_secs(31),
+            None,
+            None,
+            Duration::from_secs(30),
+        );
+        assert_eq!(decision, RecycleDecision::NeedsHealthCheck);
+    }
+}
@@ -1,6 +1,9 @@
 use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 
 use crate::error::Result;
+use crate::metrics::PoolStats;
+use crate::pool::{is_token_expired, relogin, Credentials, SharedJwt};
 use crate::DgraphConn;
 use crate::{
     api::{Mutation, Request as DgraphRequest, Response, TxnContext},
@@ -21,10 +24,18 @@ pub struct Transaction {
     dropped: bool,
     // 使用 Option 同样是为了方便复制，配合 Drop 取消事务
     conn: Option<DgraphConn>,
+    jwt: SharedJwt,
+    credentials: Option<Credentials>,
+    stats: Arc<PoolStats>,
 }
 
 impl Transaction {
-    pub fn new(conn: DgraphConn) -> Self {
+    pub fn new(
+        conn: DgraphConn,
+        jwt: SharedJwt,
+        credentials: Option<Credentials>,
+        stats: Arc<PoolStats>,
+    ) -> Self {
         Self {
             conn: Some(conn),
             keys: HashSet::new(),
@@ -33,6 +44,9 @@ impl Transaction {
             finished: false,
             mutated: false,
             dropped: false,
+            jwt,
+            credentials,
+            stats,
         }
     }
 
@@ -47,19 +61,35 @@ impl Transaction {
                 "Transaction already finished".to_string(),
             ));
         }
-        if !req.mutations.is_empty() {
+        let is_mutation = !req.mutations.is_empty();
+        if is_mutation {
             self.mutated = true;
         }
         req.start_ts = self.ctx.start_ts;
         req.hash = std::mem::take(&mut self.ctx.hash);
         let commit_now = req.commit_now;
+        let stats = Arc::clone(&self.stats);
+        let _in_flight = stats.record_request_start();
         // 事务执行失败也不要紧，drop 方法会取消事务
-        let response = self.conn.as_mut().unwrap().query(req).await?;
+        let conn = self.conn.as_mut().unwrap();
+        let response = match conn.query(req.clone()).await {
+            Ok(response) => response,
+            Err(status) if is_token_expired(&status) => {
+                relogin(conn, &self.jwt, &self.credentials).await?;
+                conn.query(req).await?
+            }
+            Err(status) => return Err(status.into()),
+        };
 
         if commit_now {
             self.finished = true;
         }
         let mut resp = response.into_inner();
+        if is_mutation {
+            self.stats.record_mutation(resp.latency.as_ref());
+        } else {
+            self.stats.record_query(resp.latency.as_ref());
+        }
         if let Some(ctx) = resp.txn.take() {
             self.merge_context(ctx)?;
         }
@@ -178,13 +208,31 @@ impl Transaction {
         for i in std::mem::take(&mut self.preds) {
             self.ctx.preds.push(i);
         }
-        self.conn
-            .as_mut()
-            .unwrap()
-            .commit_or_abort(std::mem::take(&mut self.ctx))
-            .await?;
+        let conn = self.conn.as_mut().unwrap();
+        let ctx = std::mem::take(&mut self.ctx);
+        match conn.commit_or_abort(ctx.clone()).await {
+            Ok(_) => {}
+            Err(status) if is_token_expired(&status) => {
+                relogin(conn, &self.jwt, &self.credentials).await?;
+                if let Err(status) = conn.commit_or_abort(ctx).await {
+                    return Err(self.classify_commit_error(status));
+                }
+            }
+            Err(status) => return Err(self.classify_commit_error(status)),
+        }
         Ok(())
     }
+
+    /// Converts a failed `CommitOrAbort` status into a `DgraphError`, making
+    /// sure the aborted-txn counter sees it regardless of whether the caller
+    /// went through `DgraphPool::run_txn` or drove the transaction directly.
+    fn classify_commit_error(&self, status: tonic::Status) -> DgraphError {
+        let err = DgraphError::from(status);
+        if matches!(err, DgraphError::Aborted(_)) {
+            self.stats.record_aborted_txn();
+        }
+        err
+    }
 }
 
 impl Drop for Transaction {